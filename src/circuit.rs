@@ -1,175 +1,293 @@
-use crate::{bit::Bit, gate::Gate};
-
-const MSB_MASK: u64 = 1 << 63;
-const IDX_MASK: u64 = !MSB_MASK; // first bit zero other ones
-
-/// A circuit that consists of binary boolean gates connected to each other.
-/// It is represented as a binary tree in a vec for compact representation.
-/// Example circuit representation:
-///
-///         [Gate A]                   [Gate B]
-///          /    \                     /    \
-///     [In 1]  [In 2]            [Gate C]  [In 3]
-///                               /      \
-///                           [In 1]    [In 3]
-/// Each would be a circuit tree.
-struct Circuit {
-    // The gates in the circuit, which will be accessed by index later
-    pub gates: Vec<Gate<2>>,
-    // Inputs to the circuit
-    pub input: Vec<Bit>,
-    // The circuit trees, root of each represent the output
-    pub circuit_trees: Vec<CircuitTree>,
-    // Memoized evaluations of each gate, length must be same as `gates`
-    pub gate_evals: Vec<Option<Bit>>, // TODO: think about parallel access/evaluation
+use std::io::{self, BufRead};
+
+use crate::gate::{Bit, Gate, ANDGATE, INVGATE, NANDGATE, ORGATE, XORGATE};
+
+/// A boolean circuit represented as a directed acyclic graph of binary gates,
+/// matching how real garbled-circuit engines store circuits. Each gate names
+/// the source of its two inputs ([`WireRef`]); a gate's output can feed any
+/// number of later gates, so fan-out and shared sub-circuits are first class.
+pub struct Circuit {
+    /// Number of input wires contributed by each party, in order.
+    pub input_sizes: Vec<usize>,
+    /// The gates. A gate's index is the identity of the wire it produces.
+    pub gates: Vec<GateNode>,
+    /// Indices of the gates whose outputs are circuit outputs.
+    pub output_gates: Vec<usize>,
+}
+
+/// A gate together with the two wires feeding it.
+pub struct GateNode {
+    pub gate: Gate<2>,
+    pub inputs: [WireRef; 2],
+}
+
+/// The source of a gate input: either a circuit input wire or another gate's
+/// output.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum WireRef {
+    Input(usize),
+    Gate(usize),
 }
 
 impl Circuit {
-    // Evaluate the circuit on given input
-    pub fn eval(&mut self) -> Vec<Bit> {
-        let mut evals = std::mem::take(&mut self.gate_evals);
-        let output = self
-            .circuit_trees
+    /// Total number of input wires across all parties.
+    pub fn num_inputs(&self) -> usize {
+        self.input_sizes.iter().sum()
+    }
+
+    /// Evaluate the circuit on `input`, one bit per input wire in order.
+    ///
+    /// Gates are evaluated lazily from the outputs with per-gate memoization, so
+    /// a shared sub-circuit is computed exactly once regardless of how many
+    /// parents it feeds, and the gate list need not be topologically sorted.
+    pub fn eval(&self, input: &[Bit]) -> Vec<Bit> {
+        let mut memo: Vec<Option<Bit>> = vec![None; self.gates.len()];
+        self.output_gates
             .iter()
-            .map(|t| self.eval_tree(0, t, &mut evals)) // Get the value of the root gate/node
-            .collect();
-        self.gate_evals = evals;
-        output
+            .map(|&g| self.eval_gate(g, input, &mut memo))
+            .collect()
     }
 
-    pub fn eval_tree(
-        &self,
-        node_idx: u64,
-        tree: &CircuitTree,
-        evals: &mut Vec<Option<Bit>>,
-    ) -> Bit {
-        // Evaluate the children and run them on the root gate
-        match tree.get(node_idx) {
-            (NodeType::Input, iidx) => self.input[iidx as usize],
-            (NodeType::Gate, gidx) => {
-                if evals[gidx as usize].is_some() {
-                    return evals[0].unwrap();
-                }
-                let gate = &self.gates[gidx as usize];
-                let left_val = self.eval_tree(node_idx * 2 + 1, tree, evals);
-                let right_val = self.eval_tree(node_idx * 2 + 2, tree, evals);
-                let res = gate.evaluate(&[left_val, right_val]);
-                evals[gidx as usize] = Some(res);
-                res
+    fn eval_gate(&self, gidx: usize, input: &[Bit], memo: &mut Vec<Option<Bit>>) -> Bit {
+        if let Some(v) = memo[gidx] {
+            return v;
+        }
+        let node = &self.gates[gidx];
+        let a = self.eval_ref(node.inputs[0], input, memo);
+        let b = self.eval_ref(node.inputs[1], input, memo);
+        let res = node.gate.evaluate(&[a, b]);
+        memo[gidx] = Some(res);
+        res
+    }
+
+    fn eval_ref(&self, wire: WireRef, input: &[Bit], memo: &mut Vec<Option<Bit>>) -> Bit {
+        match wire {
+            WireRef::Input(i) => input[i],
+            WireRef::Gate(g) => self.eval_gate(g, input, memo),
+        }
+    }
+
+    /// Load a circuit in the standard Bristol ("Bristol Fashion") text format:
+    ///
+    /// ```text
+    /// <#gates> <#wires>
+    /// <#input values> <size>...
+    /// <#output values> <size>...
+    ///
+    /// <#inputs> <#outputs> <in_wire>... <out_wire>... <GATE>
+    /// ...
+    /// ```
+    ///
+    /// Inputs are the lowest wires and outputs the highest, matching the large
+    /// benchmark circuits (AES, SHA) shared across the garbled-circuit ecosystem.
+    pub fn from_bristol(reader: impl BufRead) -> io::Result<Self> {
+        let mut lines = reader.lines();
+
+        let header = next_nonempty(&mut lines)?;
+        let mut hdr = header.split_whitespace();
+        let _num_gates: usize = parse_field(hdr.next())?;
+        let num_wires: usize = parse_field(hdr.next())?;
+
+        let input_sizes = parse_counts(&next_nonempty(&mut lines)?)?;
+        let output_sizes = parse_counts(&next_nonempty(&mut lines)?)?;
+        let num_inputs: usize = input_sizes.iter().sum();
+        let num_outputs: usize = output_sizes.iter().sum();
+
+        // Resolve Bristol wire ids into wire references. Input wires are the
+        // lowest `num_inputs` ids; a gate's output wire resolves to that gate.
+        let mut wire_ref: Vec<Option<WireRef>> = vec![None; num_wires];
+        for (w, slot) in wire_ref.iter_mut().enumerate().take(num_inputs) {
+            *slot = Some(WireRef::Input(w));
+        }
+
+        let mut gates = Vec::new();
+        for line in lines {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
             }
+            let (node, out_wire) = parse_gate(&line, &wire_ref)?;
+            wire_ref[out_wire] = Some(WireRef::Gate(gates.len()));
+            gates.push(node);
         }
+
+        // Bristol Fashion places the output values on the highest wires.
+        let output_gates: io::Result<Vec<usize>> = (num_wires - num_outputs..num_wires)
+            .map(|w| match wire_ref[w] {
+                Some(WireRef::Gate(g)) => Ok(g),
+                _ => Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "output wire is not driven by a gate",
+                )),
+            })
+            .collect();
+
+        Ok(Self {
+            input_sizes,
+            gates,
+            output_gates: output_gates?,
+        })
     }
 }
 
-/// An Array representations of a binary tree where each item can either be a gate or input
-/// Since it represents two kinds of items, the first msb bit will be used to indicate the type and
-/// the rest represent the index of the item in some other indexed data structure.
-/// The root of the tree is at the 0th index
-struct CircuitTree {
-    inner: Vec<u64>,
+fn parse_field<T: std::str::FromStr>(tok: Option<&str>) -> io::Result<T> {
+    tok.and_then(|t| t.parse().ok())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "malformed Bristol field"))
 }
 
-impl CircuitTree {
-    pub fn new() -> Self {
-        Self { inner: Vec::new() }
-    }
-
-    /// Push gate index
-    pub fn push_gate_idx(&mut self, gidx: u64) {
-        assert!(gidx < 1 << 62); // The index should be less than 2^63
-        self.inner.push(gidx); // since gate is prefixed with 0, no need to do anything
+fn next_nonempty(lines: &mut std::io::Lines<impl BufRead>) -> io::Result<String> {
+    for line in lines.by_ref() {
+        let line = line?;
+        if !line.trim().is_empty() {
+            return Ok(line);
+        }
     }
+    Err(io::Error::new(
+        io::ErrorKind::UnexpectedEof,
+        "unexpected end of Bristol header",
+    ))
+}
 
-    /// Insert input index
-    pub fn push_input_idx(&mut self, iidx: u64) {
-        assert!(iidx < 1 << 62); // The index should be less than 2^63
-        let val = 1 << 63 | iidx; // Add bit 1 as msb
-        self.inner.push(val); // since gate is prefixed with 0, no need to do anything
+/// Parse a `<count> <size>...` header line into the list of per-party sizes.
+fn parse_counts(line: &str) -> io::Result<Vec<usize>> {
+    let mut it = line.split_whitespace();
+    let parties: usize = parse_field(it.next())?;
+    let sizes: Vec<usize> = it.filter_map(|t| t.parse().ok()).collect();
+    if sizes.len() != parties {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Bristol header count does not match listed sizes",
+        ));
     }
+    Ok(sizes)
+}
 
-    // Get the type and index, will panic if out of bound
-    pub fn get(&self, idx: u64) -> (NodeType, u64) {
-        let val = self.inner[idx as usize];
-        let msb = val & MSB_MASK;
-        let actual_idx = val & IDX_MASK;
-        (msb.into(), actual_idx)
+/// Parse one gate line and return the node plus the wire id it drives.
+fn parse_gate(line: &str, wire_ref: &[Option<WireRef>]) -> io::Result<(GateNode, usize)> {
+    let toks: Vec<&str> = line.split_whitespace().collect();
+    let kind = *toks
+        .last()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "empty Bristol gate line"))?;
+    let n_in: usize = parse_field(toks.first().copied())?;
+    let n_out: usize = parse_field(toks.get(1).copied())?;
+    if n_out != 1 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "only single-output gates are supported",
+        ));
     }
-}
+    let wires: io::Result<Vec<usize>> = toks[2..2 + n_in + n_out]
+        .iter()
+        .map(|t| parse_field(Some(*t)))
+        .collect();
+    let wires = wires?;
+    let output = wires[n_in];
 
-pub enum NodeType {
-    Gate,  // corresponds to 0
-    Input, // corresponds to 1
-}
+    let resolve = |w: usize| -> io::Result<WireRef> {
+        wire_ref[w].ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidData, "gate reads an undriven wire")
+        })
+    };
 
-impl From<u64> for NodeType {
-    fn from(value: u64) -> Self {
-        match value {
-            0 => NodeType::Gate,
-            _ => NodeType::Input,
+    let node = match kind {
+        "INV" | "NOT" => {
+            if n_in != 1 {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "INV gate must have one input",
+                ));
+            }
+            // INVGATE ignores its second input; feed the same wire twice.
+            let r = resolve(wires[0])?;
+            GateNode {
+                gate: INVGATE,
+                inputs: [r, r],
+            }
         }
-    }
+        _ => {
+            if n_in != 2 {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "binary gate must have two inputs",
+                ));
+            }
+            let gate = match kind {
+                "AND" => ANDGATE,
+                "XOR" => XORGATE,
+                "OR" => ORGATE,
+                "NAND" => NANDGATE,
+                other => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("unsupported Bristol gate `{other}`"),
+                    ))
+                }
+            };
+            GateNode {
+                gate,
+                inputs: [resolve(wires[0])?, resolve(wires[1])?],
+            }
+        }
+    };
+    Ok((node, output))
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::gate::{ANDGATE, ORGATE, XORGATE};
-
     use super::*;
 
     #[test]
-    fn test_circuit_eval() {
-        // Create some input bits
-        let input_bits = vec![
-            Bit::One,  // Input 1: true
-            Bit::Zero, // Input 2: false
-            Bit::One,  // Input 3: true
-            Bit::Zero, // Input 4: false
-        ];
-
-        // Create gates (assuming Gate<2> takes a function and two inputs)
-        let gate1 = ANDGATE; // AND gate
-        let gate2 = ORGATE; // OR gate
-        let gate3 = XORGATE; // XOR gate
-
-        // Add gates to the circuit
-        let gates = vec![gate1, gate2, gate3];
-
-        // Create the circuit tree
-        let mut circuit_tree = CircuitTree::new();
-        // Tree structure:
-        //        Gate 1 (AND)
-        //        /      \
-        //   Gate 2      Gate 3
-        //   (OR)         (XOR)
-        //   / \          /  \
-        // In1 In2     In3  In4
-
-        // Add nodes to the tree
-        circuit_tree.push_gate_idx(0); // Gate 1
-        circuit_tree.push_gate_idx(1); // Gate 2 (left child of Gate 1)
-        circuit_tree.push_gate_idx(2); // Gate 3 (right child of Gate 1)
-        circuit_tree.push_input_idx(0); // Input 1 (left child of Gate 2)
-        circuit_tree.push_input_idx(1); // Input 2 (right child of Gate 2)
-        circuit_tree.push_input_idx(2); // Input 3 (left child of Gate 3)
-        circuit_tree.push_input_idx(3); // Input 4 (right child of Gate 3)
-        let circuit_trees = vec![circuit_tree];
-        let num_gates = gates.len();
-
-        // Initialize the circuit
-        let mut circuit = Circuit {
-            gates,
-            input: input_bits,
-            circuit_trees,
-            gate_evals: (0..num_gates).map(|_| None).collect(),
-        };
+    fn test_from_bristol_eval() {
+        // out = NOT((in0 AND in1) XOR in2)
+        let src = "\
+3 6
+1 3
+1 1
+
+2 1 0 1 3 AND
+2 1 3 2 4 XOR
+1 1 4 5 INV
+";
+        let circuit = Circuit::from_bristol(src.as_bytes()).expect("parse");
+        assert_eq!(circuit.input_sizes, vec![3]);
+        assert_eq!(circuit.gates.len(), 3);
+        assert_eq!(circuit.output_gates, vec![2]);
 
-        // Evaluate the circuit
-        let result = circuit.eval();
+        assert_eq!(circuit.eval(&[Bit::One, Bit::One, Bit::Zero]), vec![Bit::Zero]);
+        assert_eq!(circuit.eval(&[Bit::One, Bit::Zero, Bit::One]), vec![Bit::Zero]);
+        assert_eq!(circuit.eval(&[Bit::Zero, Bit::Zero, Bit::Zero]), vec![Bit::One]);
+    }
 
-        // Expected result:
-        // - Gate 2 (OR): Input 1 || Input 2 = true || false = true
-        // - Gate 3 (XOR): Input 3 ^ Input 4 = true ^ false = true
-        // - Gate 1 (AND): Gate 2 && Gate 3 = true && true = true
-        assert_eq!(result, vec![Bit::One]);
+    #[test]
+    fn test_shared_subcircuit_fanout() {
+        // A gate feeding two parents: out0 = (in0 XOR in1), and both an AND and
+        // an OR consume it, exercising fan-out through the memoized evaluator.
+        let g_xor = GateNode {
+            gate: XORGATE,
+            inputs: [WireRef::Input(0), WireRef::Input(1)],
+        };
+        let g_and = GateNode {
+            gate: ANDGATE,
+            inputs: [WireRef::Gate(0), WireRef::Input(2)],
+        };
+        let g_or = GateNode {
+            gate: ORGATE,
+            inputs: [WireRef::Gate(0), WireRef::Input(2)],
+        };
+        let circuit = Circuit {
+            input_sizes: vec![3],
+            gates: vec![g_xor, g_and, g_or],
+            output_gates: vec![1, 2],
+        };
+        // in0 XOR in1 = 1; AND 1 = 1; OR 1 = 1
+        assert_eq!(
+            circuit.eval(&[Bit::One, Bit::Zero, Bit::One]),
+            vec![Bit::One, Bit::One]
+        );
+        // in0 XOR in1 = 0; AND 1 = 0; OR 1 = 1
+        assert_eq!(
+            circuit.eval(&[Bit::One, Bit::One, Bit::One]),
+            vec![Bit::Zero, Bit::One]
+        );
     }
 }
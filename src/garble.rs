@@ -1,35 +1,50 @@
-use std::{
-    collections::HashMap,
-    fmt,
-    hash::{DefaultHasher, Hash, Hasher},
-    iter::successors,
+use std::{collections::HashMap, fmt};
+
+use sha3::{
+    digest::{ExtendableOutput, Update, XofReader},
+    Shake256,
 };
 
+use crate::ggm::GgmTree;
 use crate::gate::{Bit, Gate};
 
-impl<H, E, const I: usize> fmt::Display for GarbledTable<H, E, I>
+/// Fill a 32-byte pad by absorbing each input wire key in turn into a SHAKE256
+/// sponge and squeezing extendable output. The same key order on both sides
+/// yields the same pad, which is what lets the evaluator reproduce it.
+fn shake_pad<const I: usize>(keys: &[[u8; 16]; I]) -> [u8; 32] {
+    let mut sponge = Shake256::default();
+    for key in keys {
+        sponge.update(key);
+    }
+    let mut reader = sponge.finalize_xof();
+    let mut pad = [0u8; 32];
+    reader.read(&mut pad);
+    pad
+}
+
+impl<K, E, const I: usize> fmt::Display for GarbledTable<K, E, I>
 where
-    H: Hash + fmt::Display + fmt::Debug,
-    E: fmt::Display + fmt::Debug,
+    K: std::hash::Hash + Eq + fmt::Debug,
+    E: fmt::Debug,
 {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        // Format input_enc_map
-        writeln!(f, "Input-Encoding by symmetric encryption :")?;
-        for (input, (enc1, enc2)) in &self.input_enc_map {
-            writeln!(f, "{:?} -> ({:?}, {:?})", input, enc1, enc2)?;
+        writeln!(f, "Wire keys handed out per input combination:")?;
+        for (input, keys) in &self.input_enc_map {
+            writeln!(f, "{:?} -> {:?}", input, keys)?;
         }
-        // Format input_hash_map
-        writeln!(f, "\nHashes for corresponding inputs:")?;
-        for (input, hash) in &self.input_hash_map {
-            writeln!(f, "{:?} -> {:?}", input, hash)?;
+        match &self.linear {
+            Some(coeffs) => writeln!(f, "\nLinear gate (Free-XOR), coefficients {:?}", coeffs)?,
+            None => {
+                writeln!(f, "\nGarbled rows indexed by input select bits:")?;
+                for (idx, row) in self.rows.iter().enumerate() {
+                    writeln!(f, "[{}] -> {:?}", idx, row)?;
+                }
+            }
         }
-
-        // Format hash_out_map
-        writeln!(f, "\nEncrypted output for each input hash")?;
-        for (hash, enc) in &self.hash_out_map {
-            writeln!(f, "{:?} -> {:?}", hash, enc)?;
+        writeln!(f, "\nOutput-label decoding:")?;
+        for (label, bit) in &self.output_decode {
+            writeln!(f, "{:?} -> {}", label, bit)?;
         }
-
         Ok(())
     }
 }
@@ -38,72 +53,151 @@ pub trait Garbled<const I: usize>
 where
     [(); 1 << I]:,
 {
-    type Secret: Hash + Clone;
-    type Hash: Hash + Eq + Clone;
-    type SymmetricKey; // for password
-    type Encrypted: Hash + Clone;
+    type Secret: std::hash::Hash + Clone;
+    /// A wire label; doubles as the symmetric key protecting the rows it feeds.
+    type Key: std::hash::Hash + Eq + Clone;
+    type Encrypted: Clone;
 
     fn master_secret(&self) -> Self::Secret;
     fn gate(&self) -> &Gate<I>;
 
-    fn concat(p1: Self::Encrypted, p2: Self::Encrypted) -> Self::Encrypted;
-    fn hash(p: &impl Hash) -> Self::Hash;
-    fn encrypt_with(psswd: Self::Secret, output: Bit) -> Self::Encrypted;
-    fn decrypt_with(psswd: Self::Secret, value: Self::Encrypted) -> Bit;
+    /// The global Free-XOR offset `Δ` for this garbling session. Its least
+    /// significant (select) bit is fixed to 1 so that a wire's two labels
+    /// `L0` and `L1 = L0 ⊕ Δ` always carry opposite select bits.
+    fn delta(&self) -> Self::Key;
+
+    /// XOR two labels, the one linear operation the label space must support.
+    fn xor(a: &Self::Key, b: &Self::Key) -> Self::Key;
+
+    /// The public select ("color") bit of a label, used for point-and-permute.
+    fn color(k: &Self::Key) -> usize;
 
-    // Generate secrets from secret
-    fn gen_pwds<'a>(sec: Self::Secret) -> impl Iterator<Item = Self::Secret>;
+    /// Mask `out_key` under a pad derived from the `I` input wire keys. The pad's
+    /// low half is left in the clear as a verification tag so the evaluator can
+    /// confirm the row its labels address.
+    fn encrypt_with(keys: &[Self::Key; I], out_key: &Self::Key) -> Self::Encrypted;
 
-    fn compute_garble_table(&self) -> GarbledTable<Self::Hash, Self::Encrypted, I> {
-        let pwds: Vec<Self::Secret> = Self::gen_pwds(self.master_secret()).take(12).collect();
-        assert!(pwds.len() == 12);
+    /// Recompute the pad from the evaluator's input keys and, if the tag matches,
+    /// unmask the output key.
+    fn decrypt_with(keys: &[Self::Key; I], row: &Self::Encrypted) -> Option<Self::Key>;
 
-        let concat_hash = |(p1, p2): (Self::Encrypted, Self::Encrypted)| {
-            let c = Self::concat(p1, p2);
-            Self::hash(&c)
+    // Derive the zero-labels of the wires from the master secret
+    fn gen_pwds(sec: Self::Secret) -> impl Iterator<Item = Self::Key>;
+
+    fn compute_garble_table(&self) -> GarbledTable<Self::Key, Self::Encrypted, I> {
+        let delta = self.delta();
+        // One zero-label per input wire plus one for the output wire.
+        let zeros: Vec<Self::Key> = Self::gen_pwds(self.master_secret())
+            .take(I + 1)
+            .collect();
+        assert!(zeros.len() == I + 1);
+
+        // label(wire w, value b) = zeros[w] when b = 0, else zeros[w] ⊕ Δ.
+        let label = |w: usize, b: Bit| -> Self::Key {
+            match b {
+                Bit::Zero => zeros[w].clone(),
+                Bit::One => Self::xor(&zeros[w], &delta),
+            }
         };
-        let table = self.gate().table();
-        let mut input_hash_map = HashMap::new();
+
         let mut input_enc_map = HashMap::new();
-        let mut hash_out_map = HashMap::new();
-
-        for (i, (inp, out)) in table.iter().enumerate() {
-            // Encrypt inputs and output
-            let encrypted_inputs = (
-                Self::encrypt_with(pwds[i * 3].clone(), inp[0]),
-                Self::encrypt_with(pwds[i * 3 + 1].clone(), inp[1]),
-            );
-            // let encrypted_output = Self::encrypt_with(pwds[i * 3 + 2].clone(), out.clone());
-
-            // Compute hash for encrypted inputs
-            let input_hash = concat_hash(encrypted_inputs.clone());
-
-            // Populate maps
-            input_hash_map.insert(*inp, input_hash.clone());
-            input_enc_map.insert(*inp, encrypted_inputs);
-            hash_out_map.insert(input_hash, *out);
+        for (inp, _) in self.gate().table().iter() {
+            let keys: [Self::Key; I] = std::array::from_fn(|w| label(w, inp[w]));
+            input_enc_map.insert(*inp, keys);
+        }
+
+        // Affine gates are free: the output label is an XOR of input labels, so
+        // the output zero-label is the matching XOR of the input zero-labels.
+        if let Some((coeffs, konst)) = self.gate().affine_form() {
+            let mut out_zero: Option<Self::Key> = None;
+            for (w, &c) in coeffs.iter().enumerate() {
+                if c {
+                    out_zero = Some(match out_zero {
+                        None => zeros[w].clone(),
+                        Some(acc) => Self::xor(&acc, &zeros[w]),
+                    });
+                }
+            }
+            let out_zero = out_zero.expect("affine gate has no active inputs");
+            let out_one = Self::xor(&out_zero, &delta);
+            // A constant of 1 (e.g. XNOR) just swaps which label means which bit.
+            let (bit_for_zero, bit_for_one) = if konst {
+                (Bit::One, Bit::Zero)
+            } else {
+                (Bit::Zero, Bit::One)
+            };
+            return GarbledTable {
+                input_enc_map,
+                rows: Vec::new(),
+                linear: Some(coeffs),
+                output_decode: HashMap::from([(out_zero, bit_for_zero), (out_one, bit_for_one)]),
+            };
+        }
+
+        // Nonlinear gate: a 4-entry (point-and-permute) table indexed directly by
+        // the pair of input select bits, so evaluation is an O(1) lookup.
+        let out_zero = zeros[I].clone();
+        let out_one = Self::xor(&out_zero, &delta);
+        let output_decode = HashMap::from([(out_zero.clone(), Bit::Zero), (out_one.clone(), Bit::One)]);
+
+        let mut rows: Vec<Option<Self::Encrypted>> = (0..(1 << I)).map(|_| None).collect();
+        for (inp, out) in self.gate().table().iter() {
+            let keys: [Self::Key; I] = std::array::from_fn(|w| label(w, inp[w]));
+            let slot = keys.iter().fold(0usize, |acc, k| (acc << 1) | Self::color(k));
+            let out_key = match out {
+                Bit::Zero => out_zero.clone(),
+                Bit::One => out_one.clone(),
+            };
+            rows[slot] = Some(Self::encrypt_with(&keys, &out_key));
         }
 
         GarbledTable {
-            input_hash_map,
             input_enc_map,
-            hash_out_map,
+            rows,
+            linear: None,
+            output_decode,
+        }
+    }
+
+    /// Evaluate the garbled gate from the held input labels, returning the output
+    /// label. Linear gates XOR the active input labels; nonlinear gates jump
+    /// straight to the row addressed by the labels' select bits.
+    fn evaluate(
+        table: &GarbledTable<Self::Key, Self::Encrypted, I>,
+        keys: &[Self::Key; I],
+    ) -> Option<Self::Key> {
+        if let Some(coeffs) = &table.linear {
+            let mut out: Option<Self::Key> = None;
+            for (w, &c) in coeffs.iter().enumerate() {
+                if c {
+                    out = Some(match out {
+                        None => keys[w].clone(),
+                        Some(acc) => Self::xor(&acc, &keys[w]),
+                    });
+                }
+            }
+            return out;
         }
+        let slot = keys.iter().fold(0usize, |acc, k| (acc << 1) | Self::color(k));
+        table.rows[slot]
+            .as_ref()
+            .and_then(|row| Self::decrypt_with(keys, row))
     }
 }
 
-// #[derive(Debug, Clone)]
-pub struct SimpleGarbledGate<const I: usize>
+/// A garbled gate whose rows are protected by a SHAKE256-keyed stream cipher
+/// with a built-in verification tag, wired with Free-XOR and point-and-permute.
+pub struct KeccakGarbledGate<const I: usize>
 where
     [(); 1 << I]:,
 {
-    /// master secret
+    /// master secret the wire labels are derived from
     master_secret: u64,
     /// The gate that is garbled
     gate: Gate<I>,
 }
 
-impl<const I: usize> SimpleGarbledGate<I>
+impl<const I: usize> KeccakGarbledGate<I>
 where
     [(); 1 << I]:,
 {
@@ -115,14 +209,13 @@ where
     }
 }
 
-impl<const I: usize> Garbled<I> for SimpleGarbledGate<I>
+impl<const I: usize> Garbled<I> for KeccakGarbledGate<I>
 where
     [(); 1 << I]:,
 {
     type Secret = u64;
-    type Hash = u64;
-    type SymmetricKey = u64;
-    type Encrypted = u64;
+    type Key = [u8; 16];
+    type Encrypted = [u8; 32];
 
     fn master_secret(&self) -> Self::Secret {
         self.master_secret
@@ -132,62 +225,63 @@ where
         &self.gate
     }
 
-    fn hash(v: &impl Hash) -> u64 {
-        let mut hasher = DefaultHasher::new();
-        v.hash(&mut hasher);
-        hasher.finish()
+    fn delta(&self) -> Self::Key {
+        // Derive Δ from the master secret and force its select bit to 1.
+        let s = self.master_secret.wrapping_mul(0x9E37_79B9_7F4A_7C15).wrapping_add(1);
+        let mut delta = [0u8; 16];
+        delta[..8].copy_from_slice(&s.to_le_bytes());
+        delta[8..].copy_from_slice(&s.rotate_left(29).to_le_bytes());
+        delta[0] |= 1;
+        delta
     }
-    // A very basic encrypt
-    fn encrypt_with(secret: Self::Secret, pout: Bit) -> u64 {
-        let pout: u64 = pout.into();
-        secret + pout
+
+    fn xor(a: &Self::Key, b: &Self::Key) -> Self::Key {
+        std::array::from_fn(|i| a[i] ^ b[i])
     }
 
-    // A very basic decrypt
-    fn decrypt_with(secret: Self::Secret, value: u64) -> Bit {
-        (value - secret).into()
+    fn color(k: &Self::Key) -> usize {
+        (k[0] & 1) as usize
     }
 
-    fn concat(p1: Self::Secret, p2: Self::Secret) -> Self::Secret {
-        p1 + p2
+    fn encrypt_with(keys: &[Self::Key; I], out_key: &Self::Key) -> Self::Encrypted {
+        let mut row = shake_pad(keys);
+        for i in 0..16 {
+            row[16 + i] ^= out_key[i];
+        }
+        row
     }
 
-    fn gen_pwds<'a>(sec: Self::Secret) -> impl Iterator<Item = Self::Secret> {
-        let f = |a: &Self::Secret| Some(a * 11 + 3);
-        let start = f(&sec);
-        successors(start, f)
+    fn decrypt_with(keys: &[Self::Key; I], row: &Self::Encrypted) -> Option<Self::Key> {
+        let pad = shake_pad(keys);
+        if pad[..16] != row[..16] {
+            return None;
+        }
+        let mut out_key = [0u8; 16];
+        for i in 0..16 {
+            out_key[i] = row[16 + i] ^ pad[16 + i];
+        }
+        Some(out_key)
     }
-}
 
-#[derive(Clone, Debug)]
-pub struct GarbledTable<H: Hash, E, const I: usize> {
-    pub input_hash_map: HashMap<[Bit; I], H>,
-    pub input_enc_map: HashMap<[Bit; I], (E, E)>,
-    pub hash_out_map: HashMap<H, Bit>,
+    fn gen_pwds(sec: Self::Secret) -> impl Iterator<Item = Self::Key> {
+        // Derive labels as the leaves of a GGM tree seeded from the master
+        // secret, so only the O(log n) nodes on each label's path are touched.
+        GgmTree::from_u64(sec).into_leaves()
+    }
 }
 
 #[derive(Clone, Debug)]
-pub struct PartialAppliedGarbledTable<H, E> {
-    pub inps_sorted: Vec<(E, E)>,
-    pub hash_outputs: HashMap<H, Bit>,
-}
-
-impl<H: Clone + Eq + Hash, E: Clone, const I: usize> GarbledTable<H, E, I> {
-    // TODO: make this generic
-    pub fn get_partial_applied_table(&self, inp: Bit) -> PartialAppliedGarbledTable<H, E> {
-        let mut inps: Vec<_> = self
-            .input_hash_map
-            .keys()
-            .filter(|&x| x[0] == inp)
-            .collect();
-        inps.sort_by_key(|i| i[1]);
-
-        PartialAppliedGarbledTable {
-            inps_sorted: inps
-                .iter()
-                .map(|&i| self.input_enc_map.get(i).unwrap().clone())
-                .collect(),
-            hash_outputs: self.hash_out_map.clone(),
-        }
-    }
+pub struct GarbledTable<K: std::hash::Hash + Eq, E, const I: usize> {
+    /// Input wire keys, indexed by the clear input combination. A real protocol
+    /// hands the evaluator only its own row of keys (via OT); kept whole here so
+    /// the standalone demo can look them up.
+    pub input_enc_map: HashMap<[Bit; I], [K; I]>,
+    /// Point-and-permute rows indexed by the packed input select bits. Empty for
+    /// linear gates, which carry no ciphertexts.
+    pub rows: Vec<Option<E>>,
+    /// `Some(coeffs)` when the gate is affine (Free-XOR): the output label is the
+    /// XOR of the input labels whose coefficient is set.
+    pub linear: Option<[bool; I]>,
+    /// Decoding of output wire labels back to their clear bit.
+    pub output_decode: HashMap<K, Bit>,
 }
@@ -101,6 +101,36 @@ where
         &self.table
     }
 
+    /// Recognize whether this gate is affine (XOR-decomposable) over GF(2),
+    /// i.e. `out = konst ⊕ (⊕ c_i·in_i)` for some coefficients `c` and constant
+    /// `konst`. Such gates cost no ciphertexts under Free-XOR: the garbler just
+    /// XORs the relevant input labels. Returns `None` for nonlinear gates (AND,
+    /// OR, …) which still need a garbled table.
+    pub fn affine_form(&self) -> Option<([bool; I], bool)> {
+        let konst = self.evaluate(&[Bit::Zero; I]);
+        let mut coeffs = [false; I];
+        for (i, c) in coeffs.iter_mut().enumerate() {
+            let mut unit = [Bit::Zero; I];
+            unit[i] = Bit::One;
+            *c = self.evaluate(&unit) != konst;
+        }
+        // Confirm the candidate affine form reproduces the whole table.
+        let k: u64 = konst.into();
+        for (inp, out) in self.table.iter() {
+            let mut acc = k;
+            for (i, &c) in coeffs.iter().enumerate() {
+                if c {
+                    let b: u64 = inp[i].into();
+                    acc ^= b;
+                }
+            }
+            if Bit::from(acc) != *out {
+                return None;
+            }
+        }
+        Some((coeffs, konst == Bit::One))
+    }
+
     // pub fn stack<const J: usize>(&self, other: Gate<J>, out_gate: Gate<2>) -> Gate<{ I + J }> {}
 }
 
@@ -148,6 +178,17 @@ pub const XORGATE: Gate<2> = Gate {
     ],
 };
 
+/// Unary NOT expressed as a binary gate that ignores its second input, so it
+/// can live alongside the other gates in a `Vec<Gate<2>>`.
+pub const INVGATE: Gate<2> = Gate {
+    table: [
+        ([Bit::Zero, Bit::Zero], Bit::One),
+        ([Bit::Zero, Bit::One], Bit::One),
+        ([Bit::One, Bit::Zero], Bit::Zero),
+        ([Bit::One, Bit::One], Bit::Zero),
+    ],
+};
+
 pub const NANDGATE: Gate<2> = Gate {
     table: [
         ([Bit::Zero, Bit::Zero], Bit::One),
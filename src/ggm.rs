@@ -0,0 +1,196 @@
+//! A GGM (Goldreich–Goldwasser–Micali) tree PRG for deriving wire labels.
+//!
+//! From a single root seed each node is expanded into two children by a
+//! length-doubling PRF (SHAKE256 squeezing 32 bytes from one 128-bit block),
+//! so a depth-`DEPTH` tree exposes `2^DEPTH` pseudorandom leaf labels addressed
+//! by the bits of their index. [`GgmTree::label_at`] walks only the `O(DEPTH)`
+//! nodes on the path to one leaf, so a large circuit never materializes every
+//! label up front.
+//!
+//! The tree also supports *puncturing* ([`GgmTree::puncture`]): handing out the
+//! `DEPTH` sibling seeds along one leaf's path reveals every other leaf while
+//! keeping the punctured leaf hidden — the primitive OT-extension and related
+//! sub-protocols build on.
+
+use sha3::{
+    digest::{ExtendableOutput, Update, XofReader},
+    Shake256,
+};
+
+/// Tree depth. 2^64 leaves is effectively unbounded for any real circuit while
+/// keeping a leaf index in a `u64`.
+pub const DEPTH: usize = 64;
+
+/// Expand one node into its two children with a length-doubling PRF.
+fn expand(node: &[u8; 16]) -> ([u8; 16], [u8; 16]) {
+    let mut sponge = Shake256::default();
+    sponge.update(node);
+    let mut reader = sponge.finalize_xof();
+    let mut out = [0u8; 32];
+    reader.read(&mut out);
+    let mut left = [0u8; 16];
+    let mut right = [0u8; 16];
+    left.copy_from_slice(&out[..16]);
+    right.copy_from_slice(&out[16..]);
+    (left, right)
+}
+
+/// The `level`-th bit of a leaf index, most significant first.
+fn path_bit(index: u64, level: usize) -> u8 {
+    ((index >> (DEPTH - 1 - level)) & 1) as u8
+}
+
+/// Walk `node` down the levels `from..DEPTH` following the bits of `index`.
+fn descend(mut node: [u8; 16], index: u64, from: usize) -> [u8; 16] {
+    for level in from..DEPTH {
+        let (l, r) = expand(&node);
+        node = if path_bit(index, level) == 0 { l } else { r };
+    }
+    node
+}
+
+pub struct GgmTree {
+    root: [u8; 16],
+}
+
+impl GgmTree {
+    pub fn new(root: [u8; 16]) -> Self {
+        Self { root }
+    }
+
+    /// Seed the tree from a `u64`, stretching it into a 128-bit root.
+    pub fn from_u64(seed: u64) -> Self {
+        let mut root = [0u8; 16];
+        root[..8].copy_from_slice(&seed.to_le_bytes());
+        root[8..].copy_from_slice(&seed.rotate_left(17).to_le_bytes());
+        Self::new(root)
+    }
+
+    /// The label at leaf `index`, computed by walking the `O(DEPTH)` nodes on
+    /// its path from the root.
+    pub fn label_at(&self, index: u64) -> [u8; 16] {
+        descend(self.root, index, 0)
+    }
+
+    /// A lazy iterator over the leaf labels in index order. The iterator keeps
+    /// the node stack of the current path alive and only recomputes the levels
+    /// below the point where successive indices diverge, so streaming `n`
+    /// leaves costs amortized `O(n)` expansions rather than `O(n·DEPTH)`.
+    pub fn into_leaves(self) -> Leaves {
+        Leaves {
+            nodes: [[0u8; 16]; DEPTH + 1],
+            next: 0,
+            started: false,
+            root: self.root,
+        }
+    }
+
+    /// Puncture the tree at `index`: collect the sibling seed at each level of
+    /// the path, which together recompute every leaf but `index`.
+    pub fn puncture(&self, index: u64) -> PuncturedKey {
+        let mut node = self.root;
+        let mut siblings = Vec::with_capacity(DEPTH);
+        for level in 0..DEPTH {
+            let (l, r) = expand(&node);
+            // Keep the child we do *not* descend into; descend the path child.
+            if path_bit(index, level) == 0 {
+                siblings.push(r);
+                node = l;
+            } else {
+                siblings.push(l);
+                node = r;
+            }
+        }
+        PuncturedKey { index, siblings }
+    }
+}
+
+/// A stateful DFS over the leaves in index order. `nodes[l]` holds the node at
+/// level `l` on the path of the most recently emitted leaf; advancing to the
+/// next leaf reuses the shared prefix and re-expands only the diverging suffix.
+pub struct Leaves {
+    root: [u8; 16],
+    nodes: [[u8; 16]; DEPTH + 1],
+    next: u64,
+    started: bool,
+}
+
+impl Iterator for Leaves {
+    type Item = [u8; 16];
+
+    fn next(&mut self) -> Option<[u8; 16]> {
+        let index = self.next;
+        // The first level whose bit changed since the previous leaf; the node
+        // stack above it is still valid and gets reused.
+        let from = if !self.started {
+            self.nodes[0] = self.root;
+            0
+        } else {
+            let prev = index - 1;
+            (0..DEPTH)
+                .find(|&l| path_bit(index, l) != path_bit(prev, l))
+                .expect("consecutive indices differ")
+        };
+        for level in from..DEPTH {
+            let (l, r) = expand(&self.nodes[level]);
+            self.nodes[level + 1] = if path_bit(index, level) == 0 { l } else { r };
+        }
+        self.started = true;
+        self.next += 1;
+        Some(self.nodes[DEPTH])
+    }
+}
+
+/// A tree punctured at one leaf: enough to recompute all other leaves.
+pub struct PuncturedKey {
+    index: u64,
+    siblings: Vec<[u8; 16]>,
+}
+
+impl PuncturedKey {
+    /// The leaf punctured out of this key.
+    pub fn punctured_index(&self) -> u64 {
+        self.index
+    }
+
+    /// The label at leaf `j`, or `None` for the punctured leaf.
+    pub fn label_at(&self, j: u64) -> Option<[u8; 16]> {
+        if j == self.index {
+            return None;
+        }
+        // Find the first level where j's path leaves the punctured path; the
+        // sibling seed stored there roots j's subtree.
+        let level = (0..DEPTH)
+            .find(|&l| path_bit(j, l) != path_bit(self.index, l))
+            .expect("distinct indices must diverge");
+        Some(descend(self.siblings[level], j, level + 1))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn label_at_matches_leaves() {
+        let tree = GgmTree::from_u64(0xDEAD_BEEF);
+        let first: Vec<_> = GgmTree::from_u64(0xDEAD_BEEF).into_leaves().take(8).collect();
+        for (i, leaf) in first.iter().enumerate() {
+            assert_eq!(tree.label_at(i as u64), *leaf);
+        }
+    }
+
+    #[test]
+    fn puncture_reveals_all_but_one() {
+        let tree = GgmTree::from_u64(0x1234_5678);
+        let punctured = 5u64;
+        let key = tree.puncture(punctured);
+        assert_eq!(key.label_at(punctured), None);
+        for j in 0..32u64 {
+            if j == punctured {
+                continue;
+            }
+            assert_eq!(key.label_at(j), Some(tree.label_at(j)), "leaf {j}");
+        }
+    }
+}
@@ -2,8 +2,7 @@
 use std::io;
 
 use garbled_circuits::{
-    encryption::{EncryptionScheme, SimpleEncryptionScheme},
-    garble::{Garbled, SimpleGarbledGate},
+    garble::{Garbled, KeccakGarbledGate},
     gate::{Bit, ANDGATE},
 };
 
@@ -17,111 +16,43 @@ fn pause() {
 
 fn main() {
     let alice_secret = 42;
-    let bob_secret = 24;
 
     println!(
         "\n\n\nAlice and Bob each have a bit and want to to compute the result of ANDing their bits"
     );
-    // println!("{:?}\n", ANDGATE);
     println!("However, they do not want to share their bits with each other.");
     println!();
     println!(
-        "Alice has some secret, which she uses to encrypt the inputs and outputs of the gate.\n"
+        "Alice has some secret, from which she derives a 128-bit label for each wire value.\n"
     );
-    println!("For each input and output bits, Alice encrypts them with keys generated from here secret key.\n");
+    println!("For each row of the gate, Alice masks the output label under a SHAKE256 pad keyed by the two input labels, keeping half the pad in the clear as a verification tag.\n");
 
-    let garbled_nand = SimpleGarbledGate::new(alice_secret, ANDGATE);
-    let garbled_and_table = garbled_nand.compute_garble_table();
+    let garbled_and = KeccakGarbledGate::new(alice_secret, ANDGATE);
+    let garbled_and_table = garbled_and.compute_garble_table();
 
     println!("The garbled table now looks like this:");
     println!("{}", garbled_and_table);
 
     let alice_bit = Bit::Zero;
-
-    println!(
-        "Alice now fetches the partially applied table corresponding to her bit {}",
-        alice_bit
-    );
-    pause();
-
-    let partial_applied_table = garbled_and_table.get_partial_applied_table(alice_bit);
-
-    println!("{:?}", partial_applied_table);
-    println!();
-    pause();
-
-    println!("Now we need to do oblivious transfer. We'll do commutative encryption where, for each possible input secret in an order, Alice encrypts and sends to Bob, Ea(s_1), Ea(s_2)..., Ea(s_n).");
-    println!(
-        "\nAnd bob chooses the index he wants and encrypts(Eb(Ea(s_i))) that an sends back to Alice."
-    );
-    println!("\nAlice then decrypts the value bob sent and then sends the result to Bob, Eb(s_i).");
-    println!("\nBob then decrypts it to get the key to the row he is looking for, s_i.");
-
-    println!("\n\nSTART!");
-    let garbled_to_bob = partial_applied_table.hash_outputs;
-
-    let alice_enc = SimpleEncryptionScheme(alice_secret);
-    let bob_enc = SimpleEncryptionScheme(bob_secret);
-
-    let bob_received_inputs: Vec<_> = partial_applied_table
-        .inps_sorted
-        .iter()
-        .map(|(a, b)| (alice_enc.encrypt(*a), alice_enc.encrypt(*b)))
-        .collect();
-
-    println!("Bob receives garbled circuit eval:\n{:?}", garbled_to_bob);
-    pause();
-    println!(
-        "Bob receives encrypted gate passwords:\n{:?}",
-        bob_received_inputs
-    );
-    println!(
-        "Actual gate passwords:\n{:?}",
-        partial_applied_table.inps_sorted
-    );
-    pause();
-
     let bob_bit = Bit::One;
-    let index: u64 = bob_bit.into();
-    println!(
-        "Bob's bit is {:?} which corresponds to {}th password",
-        bob_bit, index
-    );
-    pause();
-    let bob_input = bob_received_inputs[index as usize];
-    let bob_encrypted = (bob_enc.encrypt(bob_input.0), bob_enc.encrypt(bob_input.1));
 
     println!(
-        "Bob encrypts his desired input: {:?} and sends to Alice",
-        bob_encrypted,
+        "Alice's bit is {} and Bob's bit is {}. Each party takes the label for its own bit.",
+        alice_bit, bob_bit
     );
     pause();
 
-    let alice_decrypted = (
-        alice_enc.decrypt(bob_encrypted.0),
-        alice_enc.decrypt(bob_encrypted.1),
-    );
-
-    println!(
-        "Alice decrypts it to get {:?} and sends back to Bob",
-        alice_decrypted
-    );
+    // In the real protocol Bob would fetch his label via oblivious transfer; the
+    // demo just reads Alice's table for the (alice_bit, bob_bit) row of keys.
+    let keys = garbled_and_table.input_enc_map[&[alice_bit, bob_bit]];
+    println!("Input labels for this combination: {:?}", keys);
     pause();
 
-    let bob_decrypted = (
-        bob_enc.decrypt(alice_decrypted.0),
-        bob_enc.decrypt(alice_decrypted.1),
-    );
-
-    println!("Bob decrypts it to get the password {:?}", bob_decrypted);
-    pause();
+    println!("Bob uses the select bits of his two labels to pick one garbled row and decrypts it; its verification tag confirms the row.");
+    let out_label = KeccakGarbledGate::<2>::evaluate(&garbled_and_table, &keys)
+        .expect("no row verified with these labels");
+    println!("Recovered output label: {:?}", out_label);
 
-    let concatenated =
-        <SimpleGarbledGate<2> as Garbled<2>>::concat(bob_decrypted.0, bob_decrypted.1);
-    let hash = <SimpleGarbledGate<2> as Garbled<2>>::hash(&concatenated);
-    println!("Bob hashes the password to get {:?} which is the key to the garble table he received before.", hash);
-    println!(
-        "Bob uses the hash to get the result: {:?}",
-        garbled_to_bob.get(&hash)
-    );
+    let result = garbled_and_table.output_decode[&out_label];
+    println!("Decoding the output label reveals the result: {}", result);
 }
@@ -0,0 +1,374 @@
+//! 1-out-of-2 oblivious transfer over the crate's wire-label types.
+//!
+//! The sender holds a pair of labels `(m0, m1)` for an evaluator input wire and
+//! the receiver (chooser) holds a [`Bit`]; after the protocol the receiver learns
+//! exactly `m_bit` while the sender learns nothing about the choice. Two layers
+//! are provided: a Diffie–Hellman base OT ([`BaseSender`]/[`BaseReceiver`],
+//! Chou–Orlandi "simplest OT") and an IKNP-style extension
+//! ([`ExtSender`]/[`ExtReceiver`]) that amortizes many transfers from `K` base
+//! OTs so whole-circuit evaluation with dozens of evaluator inputs stays cheap.
+//!
+//! Transport is left to the caller: the `*_step` functions take the peer's
+//! serialized messages and return the next ones as bytes. The base layer sends
+//! one `Vec<u8>` per round; the extension layer sends one `Vec<u8>` per base OT,
+//! except for the final masked labels of [`ExtSender::mask_step`].
+
+use sha3::{
+    digest::{ExtendableOutput, Update, XofReader},
+    Shake256,
+};
+
+use crate::gate::Bit;
+
+/// Security parameter: the number of base OTs an extension is built from.
+pub const K: usize = 128;
+
+// --- toy group modulo a prime ----------------------------------------------
+//
+// A real deployment would use an elliptic-curve group; this POC uses the
+// Mersenne prime field `Z_p` with p = 2^61 - 1, which is enough to demonstrate
+// the masking algebra while keeping every element in a `u64`.
+
+const P: u64 = (1 << 61) - 1;
+const G: u64 = 37;
+
+fn mul_mod(a: u64, b: u64) -> u64 {
+    ((a as u128 * b as u128) % P as u128) as u64
+}
+
+fn pow_mod(mut base: u64, mut exp: u64) -> u64 {
+    base %= P;
+    let mut acc = 1u64;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            acc = mul_mod(acc, base);
+        }
+        base = mul_mod(base, base);
+        exp >>= 1;
+    }
+    acc
+}
+
+/// Modular inverse via Fermat's little theorem (`p` is prime).
+fn inv_mod(a: u64) -> u64 {
+    pow_mod(a, P - 2)
+}
+
+/// Derive `len` pseudorandom bytes from a group element (the "random oracle" H).
+fn kdf(elem: u64, len: usize) -> Vec<u8> {
+    let mut sponge = Shake256::default();
+    sponge.update(&elem.to_le_bytes());
+    let mut reader = sponge.finalize_xof();
+    let mut out = vec![0u8; len];
+    reader.read(&mut out);
+    out
+}
+
+fn xor_into(dst: &mut [u8], src: &[u8]) {
+    for (d, s) in dst.iter_mut().zip(src) {
+        *d ^= *s;
+    }
+}
+
+// --- tiny byte cursor for message (de)serialization ------------------------
+
+fn put_u64(buf: &mut Vec<u8>, v: u64) {
+    buf.extend_from_slice(&v.to_le_bytes());
+}
+
+fn get_u64(buf: &[u8], at: usize) -> u64 {
+    let mut b = [0u8; 8];
+    b.copy_from_slice(&buf[at..at + 8]);
+    u64::from_le_bytes(b)
+}
+
+// --- base OT (Chou–Orlandi) ------------------------------------------------
+
+/// Base-OT sender. Holds the two byte-string messages and drives the A/(e0,e1)
+/// rounds. Messages may be any equal length, so the extension layer can push
+/// whole matrix columns through a base OT.
+pub struct BaseSender {
+    a: u64,
+    big_a: u64,
+    m0: Vec<u8>,
+    m1: Vec<u8>,
+}
+
+impl BaseSender {
+    /// Start a transfer. `seed` supplies the sender's secret exponent (a caller
+    /// would draw it from a CSPRNG). Returns the first message `A = g^a`.
+    pub fn new(m0: Vec<u8>, m1: Vec<u8>, seed: u64) -> (Self, Vec<u8>) {
+        assert_eq!(m0.len(), m1.len(), "OT messages must share a length");
+        let a = (seed % (P - 1)) + 1;
+        let big_a = pow_mod(G, a);
+        let mut msg = Vec::new();
+        put_u64(&mut msg, big_a);
+        (Self { a, big_a, m0, m1 }, msg)
+    }
+
+    /// Consume the receiver's `B` and emit the masked pair `(e0, e1)`.
+    pub fn sender_step(&self, recv_msg: &[u8]) -> Vec<u8> {
+        let big_b = get_u64(recv_msg, 0);
+        let k0 = pow_mod(big_b, self.a);
+        // (B / A)^a = B^a · A^{-a}
+        let k1 = mul_mod(k0, pow_mod(inv_mod(self.big_a), self.a));
+        let len = self.m0.len();
+        let mut e0 = self.m0.clone();
+        xor_into(&mut e0, &kdf(k0, len));
+        let mut e1 = self.m1.clone();
+        xor_into(&mut e1, &kdf(k1, len));
+        let mut out = Vec::new();
+        out.extend_from_slice(&e0);
+        out.extend_from_slice(&e1);
+        out
+    }
+}
+
+/// Base-OT receiver. Knows its [`Bit`] choice and recovers the chosen message.
+pub struct BaseReceiver {
+    choice: Bit,
+    key: u64,
+    len: usize,
+}
+
+impl BaseReceiver {
+    /// Consume the sender's `A` and emit `B`, stashing the shared key `A^b`.
+    /// `len` is the length of the messages being transferred.
+    pub fn receiver_step(choice: Bit, sender_msg: &[u8], len: usize, seed: u64) -> (Self, Vec<u8>) {
+        let big_a = get_u64(sender_msg, 0);
+        let b = (seed % (P - 1)) + 1;
+        let gb = pow_mod(G, b);
+        // B = g^b for choice 0, A·g^b for choice 1.
+        let big_b = match choice {
+            Bit::Zero => gb,
+            Bit::One => mul_mod(big_a, gb),
+        };
+        let key = pow_mod(big_a, b); // = g^{ab} = chosen branch's k
+        let mut msg = Vec::new();
+        put_u64(&mut msg, big_b);
+        (Self { choice, key, len }, msg)
+    }
+
+    /// Consume `(e0, e1)` and return the chosen message `m_choice`.
+    pub fn finish(&self, resp: &[u8]) -> Vec<u8> {
+        let (e0, e1) = resp.split_at(self.len);
+        let chosen = match self.choice {
+            Bit::Zero => e0,
+            Bit::One => e1,
+        };
+        let mut out = chosen.to_vec();
+        xor_into(&mut out, &kdf(self.key, self.len));
+        out
+    }
+}
+
+// --- IKNP OT extension -----------------------------------------------------
+//
+// Roles are swapped for the base OTs: the extension *sender* is the base-OT
+// *receiver* (choice bits `s`), and the extension *receiver* is the base-OT
+// *sender* (transferring the columns `t_i` and `t_i ⊕ r`).
+
+const LABEL_LEN: usize = 16;
+
+const fn bytes_for_bits(n: usize) -> usize {
+    n.div_ceil(8)
+}
+
+fn get_bit(packed: &[u8], i: usize) -> u8 {
+    (packed[i / 8] >> (i % 8)) & 1
+}
+
+fn set_bit(packed: &mut [u8], i: usize, v: u8) {
+    if v & 1 == 1 {
+        packed[i / 8] |= 1 << (i % 8);
+    }
+}
+
+/// Row hash `H(j, row)` used to mask the final labels.
+fn row_hash(j: usize, row: &[u8; bytes_for_bits(K)]) -> [u8; LABEL_LEN] {
+    let mut sponge = Shake256::default();
+    sponge.update(&(j as u64).to_le_bytes());
+    sponge.update(row);
+    let mut reader = sponge.finalize_xof();
+    let mut out = [0u8; LABEL_LEN];
+    reader.read(&mut out);
+    out
+}
+
+/// Extension sender: holds `m` label pairs and learns nothing about the choices.
+pub struct ExtSender {
+    pairs: Vec<([u8; LABEL_LEN], [u8; LABEL_LEN])>,
+    s: [u8; bytes_for_bits(K)],
+    base: Vec<BaseReceiver>,
+}
+
+impl ExtSender {
+    /// Run the `K` base OTs as receiver. `base_msgs` are the extension receiver's
+    /// base-sender first messages (one `A` per base OT); returns the `B`s.
+    pub fn sender_step(
+        pairs: Vec<([u8; LABEL_LEN], [u8; LABEL_LEN])>,
+        base_msgs: &[Vec<u8>],
+        seed: u64,
+    ) -> (Self, Vec<Vec<u8>>) {
+        assert_eq!(base_msgs.len(), K);
+        let m = pairs.len();
+        let mut s = [0u8; bytes_for_bits(K)];
+        let mut base = Vec::with_capacity(K);
+        let mut replies = Vec::with_capacity(K);
+        for (i, msg) in base_msgs.iter().enumerate() {
+            // Deterministically derive each choice bit and exponent from `seed`.
+            let bit = (seed.wrapping_mul(0x9E37_79B9_7F4A_7C15).wrapping_add(i as u64) >> 7) & 1;
+            set_bit(&mut s, i, bit as u8);
+            let (recv, reply) =
+                BaseReceiver::receiver_step(Bit::from(bit), msg, bytes_for_bits(m), seed ^ i as u64);
+            base.push(recv);
+            replies.push(reply);
+        }
+        (Self { pairs, s, base }, replies)
+    }
+
+    /// Consume the receiver's masked columns `(e0, e1)` per base OT, reconstruct
+    /// the `Q` matrix and emit the masked label pairs `(y0, y1)`.
+    pub fn mask_step(&self, col_msgs: &[Vec<u8>]) -> Vec<u8> {
+        let m = self.pairs.len();
+        let col_bytes = bytes_for_bits(m);
+        // q_i = the column recovered by base OT i.
+        let mut q: Vec<Vec<u8>> = Vec::with_capacity(K);
+        for (i, recv) in self.base.iter().enumerate() {
+            q.push(recv.finish(&col_msgs[i]));
+        }
+        let mut out = Vec::with_capacity(m * 2 * LABEL_LEN);
+        for j in 0..m {
+            // Row j of Q (length K bits), and the same XORed with s.
+            let mut row = [0u8; bytes_for_bits(K)];
+            let mut row_s = [0u8; bytes_for_bits(K)];
+            for (i, qi) in q.iter().enumerate() {
+                let bit = get_bit(&qi[..col_bytes], j);
+                set_bit(&mut row, i, bit);
+                set_bit(&mut row_s, i, bit ^ get_bit(&self.s, i));
+            }
+            let mut y0 = self.pairs[j].0;
+            xor_into(&mut y0, &row_hash(j, &row));
+            let mut y1 = self.pairs[j].1;
+            xor_into(&mut y1, &row_hash(j, &row_s));
+            out.extend_from_slice(&y0);
+            out.extend_from_slice(&y1);
+        }
+        out
+    }
+}
+
+/// Extension receiver: holds `m` choice bits and recovers one label per wire.
+pub struct ExtReceiver {
+    choices: Vec<Bit>,
+    t: Vec<Vec<u8>>, // K columns, each `m` bits packed
+    base: Vec<BaseSender>,
+}
+
+impl ExtReceiver {
+    /// Build the `T` matrix and open the `K` base OTs as sender. Returns one
+    /// base-sender first message (`A`) per base OT.
+    pub fn receiver_step(choices: Vec<Bit>, seed: u64) -> (Self, Vec<Vec<u8>>) {
+        let m = choices.len();
+        let col_bytes = bytes_for_bits(m);
+        // choice vector r, packed.
+        let mut r = vec![0u8; col_bytes];
+        for (j, c) in choices.iter().enumerate() {
+            set_bit(&mut r, j, u64::from(*c) as u8);
+        }
+        let mut t = Vec::with_capacity(K);
+        let mut base = Vec::with_capacity(K);
+        let mut first = Vec::with_capacity(K);
+        for i in 0..K {
+            // Column t_i: a pseudorandom m-bit vector derived from the seed.
+            let t_i = kdf(seed.wrapping_add(0x100 + i as u64), col_bytes);
+            let mut t_i_xor_r = t_i.clone();
+            xor_into(&mut t_i_xor_r, &r);
+            let (snd, msg) = BaseSender::new(t_i.clone(), t_i_xor_r, seed.wrapping_mul(31).wrapping_add(i as u64));
+            t.push(t_i);
+            base.push(snd);
+            first.push(msg);
+        }
+        (Self { choices, t, base }, first)
+    }
+
+    /// Answer the extension sender's base-OT `B`s with the masked columns.
+    pub fn columns_step(&self, base_replies: &[Vec<u8>]) -> Vec<Vec<u8>> {
+        self.base
+            .iter()
+            .zip(base_replies)
+            .map(|(snd, b)| snd.sender_step(b))
+            .collect()
+    }
+
+    /// Consume the masked label pairs and recover one label per wire.
+    pub fn finish(&self, mask_msg: &[u8]) -> Vec<[u8; LABEL_LEN]> {
+        let m = self.choices.len();
+        let col_bytes = bytes_for_bits(m);
+        let mut out = Vec::with_capacity(m);
+        for j in 0..m {
+            // Row j of T.
+            let mut row = [0u8; bytes_for_bits(K)];
+            for i in 0..K {
+                set_bit(&mut row, i, get_bit(&self.t[i][..col_bytes], j));
+            }
+            let base = j * 2 * LABEL_LEN;
+            let chosen = match self.choices[j] {
+                Bit::Zero => &mask_msg[base..base + LABEL_LEN],
+                Bit::One => &mask_msg[base + LABEL_LEN..base + 2 * LABEL_LEN],
+            };
+            let mut label = [0u8; LABEL_LEN];
+            label.copy_from_slice(chosen);
+            xor_into(&mut label, &row_hash(j, &row));
+            out.push(label);
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn base_ot_transfers_chosen_message() {
+        let m0 = b"label-zero-000!!".to_vec();
+        let m1 = b"label-one-111!!!".to_vec();
+        for choice in [Bit::Zero, Bit::One] {
+            let (sender, a) = BaseSender::new(m0.clone(), m1.clone(), 123456);
+            let (receiver, b) = BaseReceiver::receiver_step(choice, &a, m0.len(), 987654);
+            let resp = sender.sender_step(&b);
+            let got = receiver.finish(&resp);
+            let expected = if choice == Bit::Zero { &m0 } else { &m1 };
+            assert_eq!(&got, expected);
+        }
+    }
+
+    #[test]
+    fn iknp_extension_transfers_all_choices() {
+        let choices = vec![Bit::One, Bit::Zero, Bit::One, Bit::One, Bit::Zero];
+        let pairs: Vec<_> = (0..choices.len())
+            .map(|j| {
+                let mut a = [0u8; LABEL_LEN];
+                let mut b = [0u8; LABEL_LEN];
+                a[0] = j as u8;
+                b[0] = 0x80 | j as u8;
+                (a, b)
+            })
+            .collect();
+
+        let (recv, base_first) = ExtReceiver::receiver_step(choices.clone(), 0xABCD);
+        let (sender, base_replies) = ExtSender::sender_step(pairs.clone(), &base_first, 0x1234);
+        let columns = recv.columns_step(&base_replies);
+        let mask = sender.mask_step(&columns);
+        let got = recv.finish(&mask);
+
+        for (j, c) in choices.iter().enumerate() {
+            let expected = match c {
+                Bit::Zero => pairs[j].0,
+                Bit::One => pairs[j].1,
+            };
+            assert_eq!(got[j], expected, "wire {j}");
+        }
+    }
+}
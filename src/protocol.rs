@@ -0,0 +1,558 @@
+//! A communication-agnostic two-party garbled-circuit protocol.
+//!
+//! The [`Contributor`] garbles the whole [`Circuit`], ships the garbled tables
+//! and the labels for its own inputs, and drives oblivious transfer so the
+//! [`Evaluator`] can fetch the labels for its private inputs without revealing
+//! them. The evaluator walks the garbled circuit gate by gate using the
+//! self-verifying decryption to recover each output label, sends those labels
+//! back, and the contributor decodes them and returns the result bits.
+//!
+//! Each party is an explicit enum of states. `step(msg)` consumes one incoming
+//! serialized message and optionally emits the next one, so the caller owns the
+//! transport (sync or async). Messages carry no type tag: each state decodes
+//! whatever it receives as the message it expects, so an out-of-order message
+//! is not detected as such; it either panics while decoding or is misread.
+
+use std::collections::HashMap;
+
+use crate::circuit::{Circuit, WireRef};
+use crate::gate::Bit;
+use crate::garble::{Garbled, KeccakGarbledGate};
+use crate::ot::{ExtReceiver, ExtSender};
+
+type Kg = KeccakGarbledGate<2>;
+type Label = [u8; 16];
+/// The four point-and-permute rows of one nonlinear gate (`None` for a linear
+/// gate, which carries no ciphertexts).
+type GateRows = Option<[Option<[u8; 32]>; 4]>;
+
+// Deterministic OT exponents keep the POC reproducible; a deployment would draw
+// these from a CSPRNG.
+const OT_SEED: u64 = 0x5EED_0531;
+
+fn label_of(wire: WireRef, inputs: &[Label], gates: &[Label]) -> Label {
+    match wire {
+        WireRef::Input(i) => inputs[i],
+        WireRef::Gate(g) => gates[g],
+    }
+}
+
+/// Garble the whole circuit from a seed. Gate indices are assumed topological
+/// (a gate only references lower-indexed gates), as produced by `from_bristol`.
+///
+/// Returns the per-gate rows, the `(zero, one)` label pair of every input wire,
+/// and the output-label decodings for the circuit's output gates.
+#[allow(clippy::type_complexity)]
+fn garble(
+    circuit: &Circuit,
+    seed: u64,
+) -> (Vec<GateRows>, Vec<(Label, Label)>, Vec<HashMap<Label, Bit>>) {
+    let mut gen = Kg::gen_pwds(seed);
+    let mut delta = gen.next().unwrap();
+    delta[0] |= 1; // fix the select bit so a wire's two labels differ in color
+
+    let n_in = circuit.num_inputs();
+    let in_zero: Vec<Label> = (0..n_in).map(|_| gen.next().unwrap()).collect();
+    let input_pairs: Vec<(Label, Label)> =
+        in_zero.iter().map(|z| (*z, Kg::xor(z, &delta))).collect();
+
+    let mut gate_zero: Vec<Label> = vec![[0u8; 16]; circuit.gates.len()];
+    let mut gate_rows: Vec<GateRows> = Vec::with_capacity(circuit.gates.len());
+
+    for g in 0..circuit.gates.len() {
+        let node = &circuit.gates[g];
+        let za = label_of(node.inputs[0], &in_zero, &gate_zero);
+        let zb = label_of(node.inputs[1], &in_zero, &gate_zero);
+
+        match node.gate.affine_form() {
+            // Free-XOR: the output zero-label is the XOR of the active inputs,
+            // offset by Δ for a konst=1 gate (INV, XNOR) so the stored label
+            // still represents value 0.
+            Some((coeffs, konst)) => {
+                let mut z = [0u8; 16];
+                if coeffs[0] {
+                    z = Kg::xor(&z, &za);
+                }
+                if coeffs[1] {
+                    z = Kg::xor(&z, &zb);
+                }
+                if konst {
+                    z = Kg::xor(&z, &delta);
+                }
+                gate_zero[g] = z;
+                gate_rows.push(None);
+            }
+            // Nonlinear gate: a point-and-permute table of four masked rows.
+            None => {
+                let out_zero = gen.next().unwrap();
+                let out_one = Kg::xor(&out_zero, &delta);
+                let mut rows: [Option<[u8; 32]>; 4] = [None; 4];
+                for (inp, out) in node.gate.table().iter() {
+                    let la = if inp[0] == Bit::Zero { za } else { Kg::xor(&za, &delta) };
+                    let lb = if inp[1] == Bit::Zero { zb } else { Kg::xor(&zb, &delta) };
+                    let slot = Kg::color(&la) * 2 + Kg::color(&lb);
+                    let out_label = if *out == Bit::Zero { out_zero } else { out_one };
+                    rows[slot] = Some(Kg::encrypt_with(&[la, lb], &out_label));
+                }
+                gate_zero[g] = out_zero;
+                gate_rows.push(Some(rows));
+            }
+        }
+    }
+
+    let output_decode = circuit
+        .output_gates
+        .iter()
+        .map(|&og| {
+            let z = gate_zero[og];
+            let o = Kg::xor(&z, &delta);
+            // Every stored zero-label now represents value 0, including konst
+            // affine gates (the Δ offset is folded in during garbling).
+            HashMap::from([(z, Bit::Zero), (o, Bit::One)])
+        })
+        .collect();
+
+    (gate_rows, input_pairs, output_decode)
+}
+
+/// Walk the garbled circuit with the per-wire input labels and recover one
+/// output label per output gate.
+fn evaluate(circuit: &Circuit, input_labels: &[Label], gate_rows: &[GateRows]) -> Vec<Label> {
+    let mut gate_label: Vec<Label> = vec![[0u8; 16]; circuit.gates.len()];
+    for g in 0..circuit.gates.len() {
+        let node = &circuit.gates[g];
+        let la = label_of(node.inputs[0], input_labels, &gate_label);
+        let lb = label_of(node.inputs[1], input_labels, &gate_label);
+        gate_label[g] = match &gate_rows[g] {
+            None => {
+                let (coeffs, _) = node.gate.affine_form().expect("linear gate");
+                let mut z = [0u8; 16];
+                if coeffs[0] {
+                    z = Kg::xor(&z, &la);
+                }
+                if coeffs[1] {
+                    z = Kg::xor(&z, &lb);
+                }
+                z
+            }
+            Some(rows) => {
+                let slot = Kg::color(&la) * 2 + Kg::color(&lb);
+                let row = rows[slot].expect("point-and-permute row missing");
+                Kg::decrypt_with(&[la, lb], &row).expect("row did not verify")
+            }
+        };
+    }
+    circuit.output_gates.iter().map(|&g| gate_label[g]).collect()
+}
+
+// --- message (de)serialization --------------------------------------------
+
+struct Writer(Vec<u8>);
+
+impl Writer {
+    fn new() -> Self {
+        Self(Vec::new())
+    }
+    fn u32(&mut self, v: usize) {
+        self.0.extend_from_slice(&(v as u32).to_le_bytes());
+    }
+    fn raw(&mut self, b: &[u8]) {
+        self.0.extend_from_slice(b);
+    }
+    fn bytes(&mut self, b: &[u8]) {
+        self.u32(b.len());
+        self.raw(b);
+    }
+    fn vecvec(&mut self, v: &[Vec<u8>]) {
+        self.u32(v.len());
+        for item in v {
+            self.bytes(item);
+        }
+    }
+}
+
+struct Reader<'a> {
+    b: &'a [u8],
+    p: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(b: &'a [u8]) -> Self {
+        Self { b, p: 0 }
+    }
+    fn u32(&mut self) -> usize {
+        let mut a = [0u8; 4];
+        a.copy_from_slice(&self.b[self.p..self.p + 4]);
+        self.p += 4;
+        u32::from_le_bytes(a) as usize
+    }
+    fn take(&mut self, n: usize) -> &'a [u8] {
+        let s = &self.b[self.p..self.p + n];
+        self.p += n;
+        s
+    }
+    fn bytes(&mut self) -> Vec<u8> {
+        let n = self.u32();
+        self.take(n).to_vec()
+    }
+    fn vecvec(&mut self) -> Vec<Vec<u8>> {
+        let n = self.u32();
+        (0..n).map(|_| self.bytes()).collect()
+    }
+}
+
+fn encode_vecvec(v: &[Vec<u8>]) -> Vec<u8> {
+    let mut w = Writer::new();
+    w.vecvec(v);
+    w.0
+}
+
+fn encode_labels(labels: &[Label]) -> Vec<u8> {
+    let mut w = Writer::new();
+    w.u32(labels.len());
+    for l in labels {
+        w.raw(l);
+    }
+    w.0
+}
+
+fn decode_labels(msg: &[u8]) -> Vec<Label> {
+    let mut r = Reader::new(msg);
+    let n = r.u32();
+    (0..n)
+        .map(|_| {
+            let mut l = [0u8; 16];
+            l.copy_from_slice(r.take(16));
+            l
+        })
+        .collect()
+}
+
+fn encode_bits(bits: &[Bit]) -> Vec<u8> {
+    let mut w = Writer::new();
+    w.u32(bits.len());
+    for b in bits {
+        w.raw(&[u64::from(*b) as u8]);
+    }
+    w.0
+}
+
+fn decode_bits(msg: &[u8]) -> Vec<Bit> {
+    let mut r = Reader::new(msg);
+    let n = r.u32();
+    (0..n).map(|_| Bit::from(r.take(1)[0] as u64)).collect()
+}
+
+/// Serialized garbled circuit: rows, the contributor's input labels and the
+/// base-OT replies, bundled into the contributor's first message.
+fn encode_setup(gate_rows: &[GateRows], contrib_labels: &[Label], base_replies: &[Vec<u8>]) -> Vec<u8> {
+    let mut w = Writer::new();
+    w.u32(gate_rows.len());
+    for g in gate_rows {
+        match g {
+            None => w.raw(&[0]),
+            Some(rows) => {
+                w.raw(&[1]);
+                for row in rows {
+                    match row {
+                        None => w.raw(&[0]),
+                        Some(ct) => {
+                            w.raw(&[1]);
+                            w.raw(ct);
+                        }
+                    }
+                }
+            }
+        }
+    }
+    w.u32(contrib_labels.len());
+    for l in contrib_labels {
+        w.raw(l);
+    }
+    w.vecvec(base_replies);
+    w.0
+}
+
+#[allow(clippy::type_complexity)]
+fn decode_setup(msg: &[u8]) -> (Vec<GateRows>, Vec<Label>, Vec<Vec<u8>>) {
+    let mut r = Reader::new(msg);
+    let n = r.u32();
+    let gate_rows: Vec<GateRows> = (0..n)
+        .map(|_| {
+            if r.take(1)[0] == 0 {
+                None
+            } else {
+                let mut rows: [Option<[u8; 32]>; 4] = [None; 4];
+                for row in rows.iter_mut() {
+                    if r.take(1)[0] == 1 {
+                        let mut ct = [0u8; 32];
+                        ct.copy_from_slice(r.take(32));
+                        *row = Some(ct);
+                    }
+                }
+                Some(rows)
+            }
+        })
+        .collect();
+    let nl = r.u32();
+    let contrib_labels: Vec<Label> = (0..nl)
+        .map(|_| {
+            let mut l = [0u8; 16];
+            l.copy_from_slice(r.take(16));
+            l
+        })
+        .collect();
+    let base_replies = r.vecvec();
+    (gate_rows, contrib_labels, base_replies)
+}
+
+// --- contributor state machine ---------------------------------------------
+
+/// The garbler. Convention: input party 0 is the contributor, party 1 the
+/// evaluator.
+pub enum Contributor {
+    Init {
+        circuit: Circuit,
+        seed: u64,
+        inputs: Vec<Bit>,
+    },
+    AwaitColumns {
+        ext: ExtSender,
+        output_decode: Vec<HashMap<Label, Bit>>,
+    },
+    AwaitOutputs {
+        output_decode: Vec<HashMap<Label, Bit>>,
+    },
+    Done,
+}
+
+impl Contributor {
+    pub fn new(circuit: Circuit, seed: u64, inputs: Vec<Bit>) -> Self {
+        Self::Init {
+            circuit,
+            seed,
+            inputs,
+        }
+    }
+
+    pub fn step(self, msg: &[u8]) -> (Self, Option<Vec<u8>>) {
+        match self {
+            Self::Init {
+                circuit,
+                seed,
+                inputs,
+            } => {
+                let base_first = Reader::new(msg).vecvec();
+                let (gate_rows, input_pairs, output_decode) = garble(&circuit, seed);
+
+                let contrib_size = circuit.input_sizes[0];
+                let contrib_labels: Vec<Label> = inputs
+                    .iter()
+                    .enumerate()
+                    .map(|(i, b)| {
+                        let (z, o) = input_pairs[i];
+                        if *b == Bit::One {
+                            o
+                        } else {
+                            z
+                        }
+                    })
+                    .collect();
+
+                let ot_pairs: Vec<(Label, Label)> = input_pairs[contrib_size..].to_vec();
+                let (ext, base_replies) = ExtSender::sender_step(ot_pairs, &base_first, OT_SEED);
+
+                let reply = encode_setup(&gate_rows, &contrib_labels, &base_replies);
+                (Self::AwaitColumns { ext, output_decode }, Some(reply))
+            }
+            Self::AwaitColumns { ext, output_decode } => {
+                let columns = Reader::new(msg).vecvec();
+                let mask = ext.mask_step(&columns);
+                (Self::AwaitOutputs { output_decode }, Some(mask))
+            }
+            Self::AwaitOutputs { output_decode } => {
+                let out_labels = decode_labels(msg);
+                let bits: Vec<Bit> = out_labels
+                    .iter()
+                    .zip(&output_decode)
+                    .map(|(l, dec)| dec[l])
+                    .collect();
+                (Self::Done, Some(encode_bits(&bits)))
+            }
+            Self::Done => (Self::Done, None),
+        }
+    }
+}
+
+// --- evaluator state machine -----------------------------------------------
+
+pub enum Evaluator {
+    AwaitGarbled {
+        circuit: Circuit,
+        recv: ExtReceiver,
+    },
+    AwaitMask {
+        circuit: Circuit,
+        gate_rows: Vec<GateRows>,
+        contrib_labels: Vec<Label>,
+        recv: ExtReceiver,
+    },
+    AwaitBits,
+    Done {
+        outputs: Vec<Bit>,
+    },
+}
+
+impl Evaluator {
+    /// Kick off the protocol: open the OTs for the evaluator's own input bits
+    /// and emit the first message.
+    pub fn start(circuit: Circuit, inputs: Vec<Bit>) -> (Self, Vec<u8>) {
+        let (recv, base_first) = ExtReceiver::receiver_step(inputs, OT_SEED ^ 0xFF);
+        let msg = encode_vecvec(&base_first);
+        (Self::AwaitGarbled { circuit, recv }, msg)
+    }
+
+    pub fn step(self, msg: &[u8]) -> (Self, Option<Vec<u8>>) {
+        match self {
+            Self::AwaitGarbled { circuit, recv } => {
+                let (gate_rows, contrib_labels, base_replies) = decode_setup(msg);
+                let columns = recv.columns_step(&base_replies);
+                (
+                    Self::AwaitMask {
+                        circuit,
+                        gate_rows,
+                        contrib_labels,
+                        recv,
+                    },
+                    Some(encode_vecvec(&columns)),
+                )
+            }
+            Self::AwaitMask {
+                circuit,
+                gate_rows,
+                contrib_labels,
+                recv,
+            } => {
+                let eval_labels = recv.finish(msg);
+                let mut input_labels = contrib_labels;
+                input_labels.extend(eval_labels);
+                let out_labels = evaluate(&circuit, &input_labels, &gate_rows);
+                (Self::AwaitBits, Some(encode_labels(&out_labels)))
+            }
+            Self::AwaitBits => {
+                let outputs = decode_bits(msg);
+                (Self::Done { outputs }, None)
+            }
+            Self::Done { outputs } => (Self::Done { outputs }, None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::circuit::GateNode;
+    use crate::gate::{ANDGATE, INVGATE, XORGATE};
+
+    /// Drive both state machines to completion over an in-memory channel.
+    fn run(circuit_builder: impl Fn() -> Circuit, a_bits: Vec<Bit>, b_bits: Vec<Bit>) -> Vec<Bit> {
+        let mut contributor = Contributor::new(circuit_builder(), 0xC0FFEE, a_bits);
+        let (mut evaluator, mut msg) = Evaluator::start(circuit_builder(), b_bits);
+
+        // Evaluator speaks first; alternate until the evaluator is Done.
+        loop {
+            let (c, creply) = contributor.step(&msg);
+            contributor = c;
+            let reply = match creply {
+                Some(r) => r,
+                None => break,
+            };
+            let (e, ereply) = evaluator.step(&reply);
+            evaluator = e;
+            match ereply {
+                Some(r) => msg = r,
+                None => break,
+            }
+        }
+
+        match evaluator {
+            Evaluator::Done { outputs } => outputs,
+            _ => panic!("evaluator did not finish"),
+        }
+    }
+
+    #[test]
+    fn two_party_and() {
+        let build = || Circuit {
+            input_sizes: vec![1, 1],
+            gates: vec![GateNode {
+                gate: ANDGATE,
+                inputs: [WireRef::Input(0), WireRef::Input(1)],
+            }],
+            output_gates: vec![0],
+        };
+        for a in [Bit::Zero, Bit::One] {
+            for b in [Bit::Zero, Bit::One] {
+                let got = run(build, vec![a], vec![b]);
+                let expected = build().eval(&[a, b]);
+                assert_eq!(got, expected, "AND({a}, {b})");
+            }
+        }
+    }
+
+    #[test]
+    fn two_party_inv_feeding_and() {
+        // out = NOT(a0) AND b0 — an affine konst gate feeding a nonlinear one,
+        // which is exactly the case the plain-XOR zero-label bug got wrong.
+        let build = || Circuit {
+            input_sizes: vec![1, 1],
+            gates: vec![
+                GateNode {
+                    gate: INVGATE,
+                    inputs: [WireRef::Input(0), WireRef::Input(0)],
+                },
+                GateNode {
+                    gate: ANDGATE,
+                    inputs: [WireRef::Gate(0), WireRef::Input(1)],
+                },
+            ],
+            output_gates: vec![1],
+        };
+        for a in [Bit::Zero, Bit::One] {
+            for b in [Bit::Zero, Bit::One] {
+                let got = run(build, vec![a], vec![b]);
+                let expected = build().eval(&[a, b]);
+                assert_eq!(got, expected, "NOT({a}) AND {b}");
+            }
+        }
+    }
+
+    #[test]
+    fn two_party_and_xor_mix() {
+        // out = (a0 AND b0) XOR (a1 AND b1)
+        let build = || Circuit {
+            input_sizes: vec![2, 2],
+            gates: vec![
+                GateNode {
+                    gate: ANDGATE,
+                    inputs: [WireRef::Input(0), WireRef::Input(2)],
+                },
+                GateNode {
+                    gate: ANDGATE,
+                    inputs: [WireRef::Input(1), WireRef::Input(3)],
+                },
+                GateNode {
+                    gate: XORGATE,
+                    inputs: [WireRef::Gate(0), WireRef::Gate(1)],
+                },
+            ],
+            output_gates: vec![2],
+        };
+        // inputs are ordered party0 wires then party1 wires: [a0,a1,b0,b1].
+        let a = vec![Bit::One, Bit::Zero];
+        let b = vec![Bit::One, Bit::One];
+        let got = run(build, a.clone(), b.clone());
+        let expected = build().eval(&[a[0], a[1], b[0], b[1]]);
+        assert_eq!(got, expected);
+    }
+}